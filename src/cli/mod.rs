@@ -8,6 +8,25 @@ pub struct Opts {
     pub dir: PathBuf,
     #[arg(short, long, default_value_t = 8080)]
     pub port: u16,
+    /// Skip the check that rejects paths resolving outside the served
+    /// directory with `403 Forbidden`: symlinks pointing outside it are
+    /// followed, and `..` components climbing out of it are not rejected.
+    #[arg(long, default_value_t = false)]
+    pub follow_symlinks: bool,
+    /// Custom Handlebars template used to render directory listings,
+    /// falling back to the built-in template when not set.
+    #[arg(long)]
+    pub template: Option<PathBuf>,
+    /// PEM certificate chain to serve over HTTPS. Requires `--key`.
+    #[arg(long)]
+    pub cert: Option<PathBuf>,
+    /// PEM private key matching `--cert`. Requires `--cert`.
+    #[arg(long)]
+    pub key: Option<PathBuf>,
+    /// Require HTTPS: fail to start instead of falling back to plaintext
+    /// when `--cert`/`--key` are missing.
+    #[arg(long, default_value_t = false)]
+    pub tls: bool,
 }
 
 fn verify_path(path: &str) -> Result<PathBuf, &'static str> {
@@ -0,0 +1,104 @@
+use axum::http::{header, HeaderMap};
+use std::time::{Duration, SystemTime};
+
+/// Cache validators for a served file: a weak `ETag` and a `Last-Modified`
+/// date, both derived from the file's size and mtime.
+#[derive(Debug, Clone)]
+pub struct Validators {
+    pub etag: String,
+    pub last_modified: SystemTime,
+}
+
+impl Validators {
+    pub fn new(file_len: u64, modified: SystemTime) -> Self {
+        let mtime_secs = modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            etag: format!("W/\"{file_len}-{mtime_secs}\""),
+            last_modified: modified,
+        }
+    }
+
+    pub fn last_modified_header(&self) -> String {
+        httpdate::fmt_http_date(self.last_modified)
+    }
+
+    /// Whether the request's conditional headers indicate the client's
+    /// cached copy is still fresh, i.e. the response should be a bare
+    /// `304 Not Modified` rather than the full body.
+    pub fn is_not_modified(&self, headers: &HeaderMap) -> bool {
+        if let Some(if_none_match) = headers
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+        {
+            return if_none_match
+                .split(',')
+                .map(str::trim)
+                .any(|tag| tag == self.etag || tag == "*");
+        }
+
+        if let Some(if_modified_since) = headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+        {
+            if let Ok(since) = httpdate::parse_http_date(if_modified_since) {
+                return since >= truncate_to_secs(self.last_modified);
+            }
+        }
+
+        false
+    }
+}
+
+/// HTTP-dates only carry whole-second precision, so mtimes must be truncated
+/// the same way before comparing against a parsed `If-Modified-Since` value.
+fn truncate_to_secs(t: SystemTime) -> SystemTime {
+    let secs = t
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers_with(name: header::HeaderName, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_matching_etag_is_not_modified() {
+        let v = Validators::new(100, SystemTime::UNIX_EPOCH + Duration::from_secs(1_000));
+        let headers = headers_with(header::IF_NONE_MATCH, &v.etag);
+        assert!(v.is_not_modified(&headers));
+    }
+
+    #[test]
+    fn test_mismatched_etag_is_modified() {
+        let v = Validators::new(100, SystemTime::UNIX_EPOCH + Duration::from_secs(1_000));
+        let headers = headers_with(header::IF_NONE_MATCH, "W/\"different\"");
+        assert!(!v.is_not_modified(&headers));
+    }
+
+    #[test]
+    fn test_if_modified_since_at_or_after_mtime_is_not_modified() {
+        let v = Validators::new(100, SystemTime::UNIX_EPOCH + Duration::from_secs(1_000));
+        let headers = headers_with(header::IF_MODIFIED_SINCE, &v.last_modified_header());
+        assert!(v.is_not_modified(&headers));
+    }
+
+    #[test]
+    fn test_if_modified_since_before_mtime_is_modified() {
+        let v = Validators::new(100, SystemTime::UNIX_EPOCH + Duration::from_secs(1_000));
+        let stale = httpdate::fmt_http_date(SystemTime::UNIX_EPOCH + Duration::from_secs(500));
+        let headers = headers_with(header::IF_MODIFIED_SINCE, &stale);
+        assert!(!v.is_not_modified(&headers));
+    }
+}
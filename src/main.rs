@@ -1,31 +1,82 @@
 use anyhow::Result;
 use axum::{
-    extract::{Path, State},
-    http::{header, StatusCode},
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{Html, IntoResponse, Response},
     routing::get,
     Router,
 };
 use clap::Parser;
+use handlebars::Handlebars;
 use http_server::Opts;
+use hyper_util::{rt::TokioIo, service::TowerToHyperService};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tokio_util::io::ReaderStream;
 use tower_http::services::ServeDir;
 use tracing::{info, warn};
 
+/// Characters that must be percent-encoded within a single path segment of a
+/// listing link; letters, digits and a few URL-safe punctuation marks pass
+/// through unescaped.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Percent-encodes each `/`-separated segment of `path` independently,
+/// leaving the slashes themselves untouched.
+pub(crate) fn encode_path_segments(path: &str) -> String {
+    path.split('/')
+        .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+mod conditional;
+mod listing;
+mod range;
+mod sandbox;
+mod tls;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
     let opts = Opts::parse();
-    process_http_server(opts.dir, opts.port).await?;
+    process_http_server(
+        opts.dir,
+        opts.port,
+        opts.follow_symlinks,
+        opts.template,
+        opts.cert,
+        opts.key,
+        opts.tls,
+    )
+    .await?;
 
     Ok(())
 }
 
-pub async fn process_http_server(path: PathBuf, port: u16) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub async fn process_http_server(
+    path: PathBuf,
+    port: u16,
+    follow_symlinks: bool,
+    template: Option<PathBuf>,
+    cert: Option<PathBuf>,
+    key: Option<PathBuf>,
+    tls_required: bool,
+) -> Result<()> {
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     info!("serving {:?} on {}", path, port);
 
-    let state = HttpServerState { path: path.clone() };
+    let state = HttpServerState {
+        path: path.clone(),
+        follow_symlinks,
+        renderer: listing::build_registry(template.as_deref())?,
+    };
     let router = Router::new()
         //  static server
         .nest_service("/tower", ServeDir::new(path))
@@ -33,32 +84,95 @@ pub async fn process_http_server(path: PathBuf, port: u16) -> Result<()> {
         .route("/*path", get(handler))
         .with_state(Arc::new(state));
 
+    match (cert, key) {
+        (Some(cert), Some(key)) => serve_tls(addr, router, &cert, &key).await,
+        (None, None) if !tls_required => {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            axum::serve(listener, router).await?;
+            Ok(())
+        }
+        _ => anyhow::bail!("--tls requires both --cert and --key"),
+    }
+}
+
+/// Serves `router` over HTTPS, terminating TLS with `cert`/`key` before
+/// handing each connection to the same handlers used for plaintext.
+async fn serve_tls(
+    addr: SocketAddr,
+    router: Router,
+    cert: &std::path::Path,
+    key: &std::path::Path,
+) -> Result<()> {
+    let acceptor = tls::build_acceptor(cert, key)?;
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, router).await?;
-    Ok(())
+    info!("TLS enabled, serving {:?} on {}", cert, addr);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+
+        tokio::spawn(async move {
+            let tls_stream = match acceptor.accept(stream).await {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("TLS handshake with {peer} failed: {:?}", e);
+                    return;
+                }
+            };
+
+            let io = TokioIo::new(tls_stream);
+            let service = TowerToHyperService::new(router);
+            if let Err(e) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(io, service)
+                .await
+            {
+                warn!("connection with {peer} failed: {:?}", e);
+            }
+        });
+    }
 }
 
 #[derive(Debug)]
 struct HttpServerState {
     path: PathBuf,
+    follow_symlinks: bool,
+    renderer: Handlebars<'static>,
 }
 
-async fn root_handler(State(state): State<Arc<HttpServerState>>) -> (StatusCode, Html<String>) {
-    match list_dir(&state.path, &state.path).await {
-        Ok(body) => (StatusCode::OK, Html(body)),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Html(e.to_string())),
+async fn root_handler(
+    State(state): State<Arc<HttpServerState>>,
+    Query(query): Query<listing::ListingQuery>,
+) -> Response {
+    match listing::render(&state.path, &state.path, &query, &state.renderer).await {
+        Ok(body) => Html(body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Html(e.to_string())).into_response(),
     }
 }
 
-async fn handler(Path(path): Path<String>, State(state): State<Arc<HttpServerState>>) -> Response {
+async fn handler(
+    Path(path): Path<String>,
+    State(state): State<Arc<HttpServerState>>,
+    Query(query): Query<listing::ListingQuery>,
+    headers: HeaderMap,
+) -> Response {
     // let path = path.unwrap_or_else(|| "/".to_string());
-    let p = std::path::Path::new(&state.path).join(path);
+    // axum's `Path<String>` extractor already percent-decodes this segment;
+    // decoding it again would corrupt filenames containing a literal `%`.
+    let p = match sandbox::resolve(&state.path, std::path::Path::new(&path), state.follow_symlinks)
+    {
+        Ok(p) => p,
+        Err(sandbox::SandboxError::Forbidden) => {
+            warn!("Rejected path escaping served root: {}", path);
+            return StatusCode::FORBIDDEN.into_response();
+        }
+    };
     info!("handle: {:?}", p);
     if !p.exists() {
         info!("Path {} not found", p.display());
         StatusCode::NOT_FOUND.into_response()
     } else if p.is_dir() {
-        match list_dir(p.as_path(), &state.path).await {
+        match listing::render(p.as_path(), &state.path, &query, &state.renderer).await {
             Ok(body) => Html(body).into_response(),
             Err(e) => {
                 warn!("{}", e.to_string());
@@ -67,55 +181,121 @@ async fn handler(Path(path): Path<String>, State(state): State<Arc<HttpServerSta
         }
     } else {
         // file
-        match tokio::fs::read(&p).await {
-            Ok(content) => {
-                info!("Read {} bytes", content.len());
-                let mime_type = mime_guess::from_path(&p).first_or_text_plain();
+        let metadata = match tokio::fs::metadata(&p).await {
+            Ok(m) => m,
+            Err(e) => {
+                warn!("Error reading file metadata: {:?}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+        let file_len = metadata.len();
+        let mime_type = mime_guess::from_path(&p).first_or_text_plain();
+        let validators = metadata
+            .modified()
+            .ok()
+            .map(|m| conditional::Validators::new(file_len, m));
 
-                let mut res = content.into_response();
+        if let Some(v) = &validators {
+            if v.is_not_modified(&headers) {
+                let mut res = StatusCode::NOT_MODIFIED.into_response();
+                apply_validators(&mut res, v);
+                return res;
+            }
+        }
 
-                let h = res.headers_mut();
-                h.insert(header::CONTENT_TYPE, mime_type.to_string().parse().unwrap());
-                // h.insert(header::CACHE_CONTROL, "max-age=86400".parse().unwrap());
+        let range_header = headers
+            .get(header::RANGE)
+            .and_then(|v| v.to_str().ok())
+            .map(|h| range::parse(h, file_len));
 
+        match range_header {
+            Some(Ok(Some(r))) => serve_range(&p, file_len, r, mime_type, validators.as_ref()).await,
+            Some(Err(range::RangeError::Unsatisfiable)) => {
+                let mut res = StatusCode::RANGE_NOT_SATISFIABLE.into_response();
+                res.headers_mut().insert(
+                    header::CONTENT_RANGE,
+                    format!("bytes */{file_len}").parse().unwrap(),
+                );
                 res
             }
-            Err(e) => {
-                warn!("Error reading file: {:?}", e);
-                StatusCode::INTERNAL_SERVER_ERROR.into_response()
-            }
+            Some(Ok(None)) | None => serve_full(&p, file_len, mime_type, validators.as_ref()).await,
+        }
+    }
+}
+
+/// Sets the `ETag` / `Last-Modified` response headers from `v`.
+fn apply_validators(res: &mut Response, v: &conditional::Validators) {
+    let h = res.headers_mut();
+    h.insert(header::ETAG, v.etag.parse().unwrap());
+    h.insert(header::LAST_MODIFIED, v.last_modified_header().parse().unwrap());
+}
+
+async fn serve_full(
+    path: &std::path::Path,
+    file_len: u64,
+    mime_type: mime_guess::Mime,
+    validators: Option<&conditional::Validators>,
+) -> Response {
+    let file = match tokio::fs::File::open(path).await {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("Error opening file: {:?}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
         }
+    };
+
+    let body = Body::from_stream(ReaderStream::new(file));
+    let mut res = body.into_response();
+
+    let h = res.headers_mut();
+    h.insert(header::CONTENT_TYPE, mime_type.to_string().parse().unwrap());
+    h.insert(header::CONTENT_LENGTH, file_len.into());
+    h.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    // h.insert(header::CACHE_CONTROL, "max-age=86400".parse().unwrap());
+    if let Some(v) = validators {
+        apply_validators(&mut res, v);
     }
+
+    res
 }
 
-async fn list_dir(
-    dir_path: &std::path::Path,
-    base_path: &std::path::Path,
-) -> anyhow::Result<String> {
-    let mut entries = tokio::fs::read_dir(dir_path).await?;
-    let mut body = String::new();
-    body.push_str("<html><body><ul>");
-
-    while let Some(entry) = entries.next_entry().await? {
-        let file_name = entry.file_name();
-        let file_name_str = file_name.to_string_lossy();
-        let file_path = std::path::Path::new(&dir_path).join(&file_name);
-
-        let metadata = entry.metadata().await?;
-
-        let icon = if metadata.is_dir() { "📁" } else { "📄" };
-
-        // 使用绝对路径 /跳转 /a/b/c  -> http://xxx/a/b/c
-        let displayed_path = file_path.strip_prefix(base_path)?;
-        body.push_str(&format!(
-            "<li>{} <a href=\"/{}\">{}</a>  - {} bytes</li>",
-            icon,
-            displayed_path.to_string_lossy(),
-            file_name_str,
-            metadata.len()
-        ));
+async fn serve_range(
+    path: &std::path::Path,
+    file_len: u64,
+    r: range::ByteRange,
+    mime_type: mime_guess::Mime,
+    validators: Option<&conditional::Validators>,
+) -> Response {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let mut file = match tokio::fs::File::open(path).await {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("Error opening file for range read: {:?}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if let Err(e) = file.seek(std::io::SeekFrom::Start(r.start)).await {
+        warn!("Error seeking file: {:?}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
     }
-    body.push_str("</ul></body></html>");
 
-    Ok(body)
+    let slice_len = r.end - r.start + 1;
+    let body = Body::from_stream(ReaderStream::new(file.take(slice_len)));
+    let mut res = (StatusCode::PARTIAL_CONTENT, body).into_response();
+
+    let h = res.headers_mut();
+    h.insert(header::CONTENT_TYPE, mime_type.to_string().parse().unwrap());
+    h.insert(
+        header::CONTENT_RANGE,
+        format!("bytes {}-{}/{file_len}", r.start, r.end)
+            .parse()
+            .unwrap(),
+    );
+    h.insert(header::CONTENT_LENGTH, slice_len.into());
+    h.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Some(v) = validators {
+        apply_validators(&mut res, v);
+    }
+    res
 }
@@ -0,0 +1,119 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// An inclusive byte range resolved against a known file length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// Why a `Range` header could not be honored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeError {
+    /// The header was well-formed but describes a range outside the file.
+    Unsatisfiable,
+}
+
+fn pattern() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^bytes=(\d*)-(\d*)$").unwrap())
+}
+
+/// Parses a single `Range` header value into its raw `start`/`end` bounds.
+fn parse_bounds(header: &str) -> Option<(Option<u64>, Option<u64>)> {
+    let caps = pattern().captures(header)?;
+    let start = caps[1].parse::<u64>().ok();
+    let end = caps[2].parse::<u64>().ok();
+    if start.is_none() && end.is_none() {
+        return None;
+    }
+    Some((start, end))
+}
+
+/// Parses and resolves a `Range` header against a file of `file_len` bytes.
+///
+/// Returns `Ok(None)` when the header is absent or malformed (the caller
+/// should fall back to a full `200` response), `Ok(Some(range))` for a
+/// satisfiable range, or `Err(RangeError::Unsatisfiable)` when the header is
+/// well-formed but describes a range outside the file.
+pub fn parse(header: &str, file_len: u64) -> Result<Option<ByteRange>, RangeError> {
+    let Some((start, end)) = parse_bounds(header) else {
+        return Ok(None);
+    };
+
+    let (start, end) = match (start, end) {
+        (Some(start), Some(end)) => (start, end.min(file_len.saturating_sub(1))),
+        (Some(start), None) => (start, file_len.saturating_sub(1)),
+        (None, Some(suffix_len)) => (
+            file_len.saturating_sub(suffix_len),
+            file_len.saturating_sub(1),
+        ),
+        (None, None) => unreachable!("parse_bounds rejects empty ranges"),
+    };
+
+    if file_len == 0 || start > end || start >= file_len {
+        return Err(RangeError::Unsatisfiable);
+    }
+
+    Ok(Some(ByteRange { start, end }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_closed_range() {
+        assert_eq!(
+            parse("bytes=0-499", 1000).unwrap(),
+            Some(ByteRange { start: 0, end: 499 })
+        );
+    }
+
+    #[test]
+    fn test_parse_open_ended_range() {
+        assert_eq!(
+            parse("bytes=500-", 1000).unwrap(),
+            Some(ByteRange {
+                start: 500,
+                end: 999
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_suffix_range() {
+        assert_eq!(
+            parse("bytes=-500", 1000).unwrap(),
+            Some(ByteRange {
+                start: 500,
+                end: 999
+            })
+        );
+    }
+
+    #[test]
+    fn test_clamps_end_to_file_len() {
+        assert_eq!(
+            parse("bytes=0-9999", 1000).unwrap(),
+            Some(ByteRange { start: 0, end: 999 })
+        );
+    }
+
+    #[test]
+    fn test_malformed_header_falls_back() {
+        assert_eq!(parse("not-a-range", 1000).unwrap(), None);
+        assert_eq!(parse("bytes=-", 1000).unwrap(), None);
+    }
+
+    #[test]
+    fn test_start_past_end_is_unsatisfiable() {
+        assert_eq!(parse("bytes=900-800", 1000), Err(RangeError::Unsatisfiable));
+    }
+
+    #[test]
+    fn test_start_past_file_len_is_unsatisfiable() {
+        assert_eq!(parse("bytes=1000-", 1000), Err(RangeError::Unsatisfiable));
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+/// Why a requested path was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SandboxError {
+    /// The resolved path escapes the served root.
+    Forbidden,
+}
+
+/// Resolves `requested` against `base`, guaranteeing the result stays inside
+/// `base` unless `follow_symlinks` opts out of the containment check.
+///
+/// Handles paths that don't exist yet (e.g. a 404 target) by canonicalizing
+/// the nearest existing ancestor instead of the path itself.
+pub fn resolve(
+    base: &Path,
+    requested: &Path,
+    follow_symlinks: bool,
+) -> Result<PathBuf, SandboxError> {
+    let joined = base.join(requested);
+
+    if follow_symlinks {
+        return Ok(joined);
+    }
+
+    let Ok(canonical_base) = base.canonicalize() else {
+        return Ok(joined);
+    };
+
+    if canonicalize_existing_ancestor(&joined).starts_with(&canonical_base) {
+        Ok(joined)
+    } else {
+        Err(SandboxError::Forbidden)
+    }
+}
+
+/// Canonicalizes `path`, walking up to the nearest existing ancestor if it
+/// (or a component of it) doesn't exist yet, then re-appending the
+/// not-yet-existing tail onto the canonicalized ancestor.
+fn canonicalize_existing_ancestor(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut tail = Vec::new();
+
+    loop {
+        match existing.canonicalize() {
+            Ok(mut canon) => {
+                for component in tail.into_iter().rev() {
+                    canon.push(component);
+                }
+                return canon;
+            }
+            Err(_) => {
+                let Some(parent) = existing.parent() else {
+                    return path.to_path_buf();
+                };
+                if let Some(name) = existing.file_name() {
+                    tail.push(name.to_owned());
+                }
+                existing = parent;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows_path_within_root() {
+        let tmp = std::env::temp_dir();
+        let base = tmp.join("sandbox_test_root");
+        std::fs::create_dir_all(&base).unwrap();
+        let resolved = resolve(&base, Path::new("a/b.txt"), false).unwrap();
+        assert!(resolved.starts_with(&base));
+    }
+
+    #[test]
+    fn test_rejects_parent_traversal() {
+        let tmp = std::env::temp_dir();
+        let base = tmp.join("sandbox_test_root2");
+        std::fs::create_dir_all(&base).unwrap();
+        let err = resolve(&base, Path::new("../etc/passwd"), false).unwrap_err();
+        assert_eq!(err, SandboxError::Forbidden);
+    }
+
+    #[test]
+    fn test_follow_symlinks_skips_containment_check() {
+        let tmp = std::env::temp_dir();
+        let base = tmp.join("sandbox_test_root3");
+        std::fs::create_dir_all(&base).unwrap();
+        assert!(resolve(&base, Path::new("../etc/passwd"), true).is_ok());
+    }
+}
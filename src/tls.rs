@@ -0,0 +1,40 @@
+use anyhow::Context;
+use rustls_pemfile::{certs, pkcs8_private_keys};
+use std::{fs::File, io::BufReader, path::Path, sync::Arc};
+use tokio_rustls::{
+    rustls::{
+        pki_types::{CertificateDer, PrivateKeyDer},
+        ServerConfig,
+    },
+    TlsAcceptor,
+};
+
+/// Loads a PEM certificate chain and private key and builds a `TlsAcceptor`
+/// ready to wrap accepted `TcpStream`s.
+pub fn build_acceptor(cert_path: &Path, key_path: &Path) -> anyhow::Result<TlsAcceptor> {
+    let certs = load_certs(cert_path)?;
+    let key = load_key(key_path)?;
+
+    let config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("building rustls server config")?;
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn load_certs(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let file = File::open(path).with_context(|| format!("opening cert file {}", path.display()))?;
+    certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("parsing cert file {}", path.display()))
+}
+
+fn load_key(path: &Path) -> anyhow::Result<PrivateKeyDer<'static>> {
+    let file = File::open(path).with_context(|| format!("opening key file {}", path.display()))?;
+    let key = pkcs8_private_keys(&mut BufReader::new(file))
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("no private key found in {}", path.display()))?
+        .with_context(|| format!("parsing key file {}", path.display()))?;
+    Ok(PrivateKeyDer::from(key))
+}